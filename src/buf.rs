@@ -0,0 +1,193 @@
+//! `bytes`-crate [`Buf`]/[`BufMut`] adapters, behind the `bytes` cargo feature.
+//!
+//! These drive the same marker-run state machine as [`stuff_slice`](crate::stuff_slice) and
+//! [`unstuff_slice`](crate::unstuff_slice), but over `Buf`/`BufMut` cursors instead of a single
+//! contiguous `&[u8]`, so frames that are chained across multiple underlying segments are handled
+//! transparently.
+
+use bytes::{Buf, BufMut};
+
+use crate::DecodeError;
+
+/// COBS-encodes all remaining bytes of `src` into `dst`, including the trailing marker byte.
+///
+/// `src` is fully drained. Just like [`stuff`](crate::stuff), this panics if `dst` doesn't have
+/// enough remaining capacity to hold the encoded data.
+///
+/// # Examples
+///
+/// ```
+/// use bytes::Buf;
+///
+/// let mut src = &b"Hello, world!"[..];
+/// let mut dst = Vec::new();
+///
+/// cobs_rs::stuff_buf(&mut src, &mut dst, 0x00);
+///
+/// assert!(!src.has_remaining());
+/// assert!(dst[..dst.len() - 1].iter().all(|byte| *byte != 0x00));
+/// ```
+pub fn stuff_buf<B: Buf, O: BufMut>(src: &mut B, dst: &mut O, marker: u8) {
+    // A run can be at most 254 bytes long before an overhead byte has to be inserted, so a
+    // fixed-size buffer is enough to stage one without needing to allocate.
+    let mut run = [0u8; 254];
+
+    loop {
+        let mut run_len = 0;
+        let mut hit_marker = false;
+
+        while run_len < run.len() {
+            if !src.has_remaining() {
+                break;
+            }
+
+            let byte = src.get_u8();
+            if byte == marker {
+                hit_marker = true;
+                break;
+            }
+
+            run[run_len] = byte;
+            run_len += 1;
+        }
+
+        // The code byte is the distance to the next overhead/marker byte.
+        dst.put_u8((run_len + 1) as u8);
+        dst.put_slice(&run[..run_len]);
+
+        if hit_marker || src.has_remaining() {
+            // Either we stopped at an actual marker, or we filled a full 254-byte run and there
+            // is more data to encode: either way, another run follows.
+            continue;
+        }
+
+        // We ran out of input without hitting a marker: terminate the frame.
+        dst.put_u8(marker);
+        return;
+    }
+}
+
+/// COBS-decodes a single frame from `src` into `dst`, stopping as soon as a marker byte is found.
+///
+/// Returns [`DecodeError::NoTerminator`] if `src` runs out before a marker byte is found, and
+/// [`DecodeError::Malformed`] if an overhead byte claims a distance that reaches past the
+/// terminator. Unlike [`unstuff_slice`](crate::unstuff_slice), this never returns
+/// [`DecodeError::OutputTooSmall`]: just like [`stuff_buf`], it panics if `dst` runs out of
+/// remaining capacity, which cannot happen with `BufMut` implementations (such as `BytesMut`)
+/// that grow on demand.
+///
+/// # Examples
+///
+/// ```
+/// let mut encoded = Vec::new();
+/// cobs_rs::stuff_buf(&mut &b"Hello, world!"[..], &mut encoded, 0x00);
+///
+/// let mut decoded = Vec::new();
+/// cobs_rs::unstuff_buf(&mut &encoded[..], &mut decoded, 0x00).unwrap();
+///
+/// assert_eq!(decoded, b"Hello, world!");
+/// ```
+pub fn unstuff_buf<B: Buf, O: BufMut>(src: &mut B, dst: &mut O, marker: u8) -> Result<(), DecodeError> {
+    if !src.has_remaining() {
+        return Err(DecodeError::NoTerminator);
+    }
+    let first = src.get_u8();
+
+    if first == marker {
+        // A leading marker byte terminates an empty frame, just like in `unstuff_slice`.
+        return Ok(());
+    }
+
+    let mut until_next_marker = first.wrapping_sub(1);
+    let mut next_is_overhead_byte = first == 0xff;
+
+    loop {
+        if !src.has_remaining() {
+            return Err(DecodeError::NoTerminator);
+        }
+        let value = src.get_u8();
+
+        if value == marker {
+            if until_next_marker != 0 {
+                return Err(DecodeError::Malformed);
+            }
+
+            return Ok(());
+        }
+
+        if until_next_marker == 0 {
+            until_next_marker = value;
+
+            if !next_is_overhead_byte {
+                dst.put_u8(marker);
+            }
+
+            next_is_overhead_byte = until_next_marker == 0xff;
+        } else {
+            dst.put_u8(value);
+        }
+
+        until_next_marker -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec;
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::stuff_slice;
+
+    fn assert_matches_stuff_slice(data: &[u8], marker: u8) {
+        let mut expected = vec![0u8; data.len() + data.len() / 254 + 2];
+        let n = stuff_slice(data, &mut expected, marker).unwrap();
+        expected.truncate(n);
+
+        let mut actual = Vec::new();
+        stuff_buf(&mut &data[..], &mut actual, marker);
+        assert_eq!(actual, expected, "stuff_buf mismatch for length {}", data.len());
+
+        let mut decoded = Vec::new();
+        unstuff_buf(&mut &actual[..], &mut decoded, marker).unwrap();
+        assert_eq!(decoded, data, "unstuff_buf mismatch for length {}", data.len());
+    }
+
+    #[test]
+    fn matches_stuff_slice_around_run_boundaries() {
+        // Exercise lengths right around the 254-byte run boundary, since that's where a
+        // forward-scanning encoder is most likely to disagree with the backpatching one used by
+        // `stuff`/`stuff_slice`.
+        for len in 250..=258 {
+            let data: Vec<u8> = (0..len).map(|i| (i % 251 + 1) as u8).collect();
+            assert_matches_stuff_slice(&data, 0x00);
+
+            // Also exercise a marker byte landing exactly on the run boundary.
+            if len > 253 {
+                let mut with_marker = data.clone();
+                with_marker[253] = 0x00;
+                assert_matches_stuff_slice(&with_marker, 0x00);
+            }
+        }
+    }
+
+    #[test]
+    fn decode_reports_no_terminator() {
+        let mut decoded = Vec::new();
+        assert_eq!(
+            unstuff_buf(&mut &[0x02, b'h'][..], &mut decoded, 0x00),
+            Err(DecodeError::NoTerminator)
+        );
+    }
+
+    #[test]
+    fn decode_reports_malformed() {
+        let mut decoded = Vec::new();
+        assert_eq!(
+            unstuff_buf(&mut &[0x09, b'b', b'a', b'd', 0x00][..], &mut decoded, 0x00),
+            Err(DecodeError::Malformed)
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Checksum-protected framing, pairing [`stuff_slice`](crate::stuff_slice) and
+//! [`unstuff_slice`](crate::unstuff_slice) with an RFC 1071 Internet checksum so the decoder can detect a frame that was corrupted in
+//! transit, rather than silently handing back damaged data.
+
+use crate::slice::stuff_from_iter;
+use crate::{unstuff_slice, DecodeError, EncodeError};
+
+/// Chains a payload's bytes with its 2 checksum bytes, without requiring the two to be assembled
+/// into one contiguous buffer first.
+#[derive(Clone)]
+struct PayloadThenChecksum<'a> {
+    payload: core::slice::Iter<'a, u8>,
+    checksum: core::array::IntoIter<u8, 2>,
+}
+
+impl Iterator for PayloadThenChecksum<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        self.payload.next().copied().or_else(|| self.checksum.next())
+    }
+}
+
+impl ExactSizeIterator for PayloadThenChecksum<'_> {
+    fn len(&self) -> usize {
+        self.payload.len() + self.checksum.len()
+    }
+}
+
+/// Computes the [RFC 1071](https://www.rfc-editor.org/rfc/rfc1071) Internet checksum over `data`.
+///
+/// Successive big-endian 16-bit words of `data` are accumulated into a 32-bit sum, carries are
+/// folded back in until none remain, and the 16-bit result is bitwise-negated. If `data` has an
+/// odd length, the final byte is padded with a zero high byte, i.e. it is treated as the low byte
+/// of the final word.
+fn internet_checksum(data: &[u8]) -> u16 {
+    let mut chunks = data.chunks_exact(2);
+
+    let mut sum: u32 = chunks
+        .by_ref()
+        .map(|word| u16::from_be_bytes([word[0], word[1]]) as u32)
+        .sum();
+
+    if let [last_byte] = *chunks.remainder() {
+        sum += u16::from_be_bytes([0, last_byte]) as u32;
+    }
+
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    !(sum as u16)
+}
+
+/// COBS-encodes `input` into the `out` buffer, with a trailing [RFC 1071] checksum of `input`
+/// appended before stuffing.
+///
+/// This lets [`unstuff_checked`] detect a frame that was corrupted in transit. Otherwise behaves
+/// just like [`stuff_slice`](crate::stuff_slice): returns the number of bytes written to `out`, and
+/// never panics, returning [`EncodeError::OutputTooSmall`] if `out` is too small instead.
+///
+/// [RFC 1071]: https://www.rfc-editor.org/rfc/rfc1071
+///
+/// # Examples
+///
+/// ```
+/// let mut out = [0u8; 64];
+/// let written = cobs_rs::stuff_checked(b"Hello, world!", &mut out, 0x00).unwrap();
+///
+/// let mut decoded = [0u8; 64];
+/// let written = cobs_rs::unstuff_checked(&out[..written], &mut decoded, 0x00).unwrap();
+///
+/// assert_eq!(&decoded[..written], b"Hello, world!");
+/// ```
+pub fn stuff_checked(input: &[u8], out: &mut [u8], marker: u8) -> Result<usize, EncodeError> {
+    let checksum = internet_checksum(input).to_be_bytes();
+
+    stuff_from_iter(
+        PayloadThenChecksum {
+            payload: input.iter(),
+            checksum: checksum.into_iter(),
+        },
+        out,
+        marker,
+    )
+}
+
+/// COBS-decodes `input` into the `out` buffer, verifying and stripping a trailing [RFC 1071]
+/// checksum that was appended by [`stuff_checked`].
+///
+/// Returns [`DecodeError::ChecksumMismatch`] if the decoded payload's checksum doesn't match,
+/// which indicates the frame was corrupted in transit. Otherwise behaves just like
+/// [`unstuff_slice`](crate::unstuff_slice).
+///
+/// [RFC 1071]: https://www.rfc-editor.org/rfc/rfc1071
+///
+/// # Examples
+///
+/// ```
+/// let mut out = [0u8; 64];
+/// let written = cobs_rs::stuff_checked(b"Hello, world!", &mut out, 0x00).unwrap();
+///
+/// // Corrupt a single payload byte in transit (`out[0]` is the leading overhead byte).
+/// out[1] ^= 0xff;
+///
+/// let mut decoded = [0u8; 64];
+/// let err = cobs_rs::unstuff_checked(&out[..written], &mut decoded, 0x00).unwrap_err();
+///
+/// assert_eq!(err, cobs_rs::DecodeError::ChecksumMismatch);
+/// ```
+pub fn unstuff_checked(input: &[u8], out: &mut [u8], marker: u8) -> Result<usize, DecodeError> {
+    let written = unstuff_slice(input, out, marker)?;
+
+    let payload_len = written
+        .checked_sub(2)
+        .ok_or(DecodeError::ChecksumMismatch)?;
+    let (payload, checksum) = out[..written].split_at(payload_len);
+
+    if internet_checksum(payload).to_be_bytes() != checksum {
+        return Err(DecodeError::ChecksumMismatch);
+    }
+
+    Ok(payload_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checksum_round_trips() {
+        let mut out = [0u8; 64];
+        let written = stuff_checked(b"Hello, world!", &mut out, 0x00).unwrap();
+
+        let mut decoded = [0u8; 64];
+        let written = unstuff_checked(&out[..written], &mut decoded, 0x00).unwrap();
+        assert_eq!(&decoded[..written], b"Hello, world!");
+    }
+
+    #[test]
+    fn odd_length_checksum_pads_final_byte_with_a_zero_high_byte() {
+        // `0x01` is the low byte of the lone word `0x0001`, padded with a zero high byte, so the
+        // checksum is the one's complement of `0x0001`, i.e. `0xfffe`, on the wire as `[0xff,
+        // 0xfe]`. Pinned explicitly since this is a wire format meant to interoperate.
+        assert_eq!(internet_checksum(&[0x01]).to_be_bytes(), [0xff, 0xfe]);
+    }
+
+    #[test]
+    fn checksum_round_trips_empty_and_odd_length_payloads() {
+        for payload in [&b""[..], &b"a"[..], &b"abc"[..]] {
+            let mut out = [0u8; 64];
+            let written = stuff_checked(payload, &mut out, 0x00).unwrap();
+
+            let mut decoded = [0u8; 64];
+            let written = unstuff_checked(&out[..written], &mut decoded, 0x00).unwrap();
+            assert_eq!(&decoded[..written], payload);
+        }
+    }
+
+    #[test]
+    fn detects_corrupted_payload() {
+        let mut out = [0u8; 64];
+        let written = stuff_checked(b"Hello, world!", &mut out, 0x00).unwrap();
+        // Flip a payload byte (`out[0]` is the leading overhead byte, not payload data).
+        out[1] ^= 0xff;
+
+        let mut decoded = [0u8; 64];
+        assert_eq!(
+            unstuff_checked(&out[..written], &mut decoded, 0x00),
+            Err(DecodeError::ChecksumMismatch)
+        );
+    }
+
+    #[test]
+    fn rejects_frame_too_short_to_hold_a_checksum() {
+        let mut out = [0u8; 64];
+        let written = crate::stuff_slice(b"a", &mut out, 0x00).unwrap();
+
+        let mut decoded = [0u8; 64];
+        assert_eq!(
+            unstuff_checked(&out[..written], &mut decoded, 0x00),
+            Err(DecodeError::ChecksumMismatch)
+        );
+    }
+}
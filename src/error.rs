@@ -0,0 +1,26 @@
+//! Error types returned by the fallible, slice-based encoding and decoding functions.
+
+/// An error that occurred while COBS-encoding data with [`stuff_slice`](crate::stuff_slice).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EncodeError {
+    /// The output buffer is not large enough to hold the encoded data.
+    OutputTooSmall {
+        /// The number of bytes the output buffer would need to be to fit the encoded data.
+        needed: usize,
+    },
+}
+
+/// An error that occurred while COBS-decoding data with [`unstuff_slice`](crate::unstuff_slice).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    /// The output buffer is not large enough to hold the decoded data.
+    OutputTooSmall,
+    /// The input buffer did not contain a marker byte to terminate the frame.
+    NoTerminator,
+    /// An overhead byte claimed a distance to the next overhead/marker byte that reaches past
+    /// where the terminator actually occurred, i.e. the frame is structurally corrupt.
+    Malformed,
+    /// The trailing checksum appended by [`stuff_checked`](crate::stuff_checked) did not match
+    /// the decoded payload, i.e. the frame was corrupted in transit.
+    ChecksumMismatch,
+}
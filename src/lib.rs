@@ -9,11 +9,32 @@
 //!
 //! ## Features
 //!
-//! The *cobs-rs* crate only provides two specific functions. Namely, the
-//! [`stuff`] and the [`unstuff`] function, which encode and decode respectively. This, together
-//! with the fact that the crate doesn't use the [`std`](https://doc.rust-lang.org/std/index.html),
-//! makes the crate perfect for embedded hardware. However, it can also be used outside of embedded
-//! systems.
+//! The *cobs-rs* crate provides the [`stuff`] and the [`unstuff`] function, which encode and
+//! decode respectively, using fixed-size `[u8; N]` buffers. This, together with the fact that the
+//! crate doesn't use the [`std`](https://doc.rust-lang.org/std/index.html), makes the crate
+//! perfect for embedded hardware. However, it can also be used outside of embedded systems.
+//!
+//! For cases where the buffer sizes aren't known at compile time, or where panicking is not an
+//! option, [`stuff_slice`] and [`unstuff_slice`] provide the same encoding and decoding over
+//! runtime-sized `&[u8]` slices, returning a [`Result`] instead of panicking.
+//!
+//! For a continuous stream of frames, such as bytes arriving from a serial port, [`FrameDecoder`]
+//! buffers incoming chunks and decodes one frame per marker boundary, recovering from malformed
+//! frames by resynchronizing at the next marker rather than aborting the whole stream.
+//! [`FrameEncoder`] is its counterpart for writing frames back-to-back into an output stream.
+//!
+//! Outside of `no_std` environments, the `alloc` cargo feature enables [`stuff_to_vec`] and
+//! [`unstuff_to_vec`], which encode and decode into an auto-growing `Vec<u8>` instead of a
+//! caller-sized buffer.
+//!
+//! The `bytes` cargo feature enables [`stuff_buf`] and [`unstuff_buf`], which encode and decode
+//! directly between [`bytes::Buf`] and [`bytes::BufMut`] cursors, for use in codec pipelines that
+//! already move data through chained, non-contiguous `bytes` buffers.
+//!
+//! COBS only removes the marker byte; it says nothing about whether a frame was corrupted in
+//! transit. [`stuff_checked`] and [`unstuff_checked`] pair the encoding with a trailing RFC 1071
+//! checksum, so a corrupted frame is reported as [`DecodeError::ChecksumMismatch`] instead of
+//! silently handed back.
 //!
 //! ## Usage
 //!
@@ -99,25 +120,28 @@
 #![no_std]
 #![warn(missing_docs)]
 
-use core::convert::TryInto;
-
-struct MarkerInfo {
-    index: usize,
-    points_to: usize,
-}
-
-impl MarkerInfo {
-    fn adjust_accordingly<const SIZE: usize>(
-        &mut self,
-        out_buffer: &mut [u8; SIZE],
-        new_index: usize,
-    ) {
-        out_buffer[self.index] = (new_index - self.index).try_into().unwrap();
-
-        self.index = new_index;
-        self.points_to = new_index + 0xff;
-    }
-}
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "bytes")]
+mod buf;
+mod checksum;
+mod error;
+mod slice;
+mod stream;
+#[cfg(feature = "alloc")]
+mod vec;
+
+#[cfg(feature = "bytes")]
+pub use buf::{stuff_buf, unstuff_buf};
+pub use checksum::{stuff_checked, unstuff_checked};
+pub use error::{DecodeError, EncodeError};
+pub use slice::{stuff_slice, unstuff_slice};
+pub use stream::{FrameDecoder, FrameEncoder};
+#[cfg(feature = "alloc")]
+pub use vec::{stuff_to_vec, unstuff_to_vec};
+
+use slice::unstuff_core;
 
 /// Takes an input buffer and a marker value and COBS-encodes it to an output buffer.
 ///
@@ -158,56 +182,20 @@ impl MarkerInfo {
 /// This function panics, if the output buffer has too little space to fill the data from the input
 /// buffer with.
 ///
+/// This is a thin wrapper around [`stuff_slice`]; see that function if you would rather receive a
+/// [`Result`] than panic.
 pub fn stuff<const INPUT: usize, const OUTPUT: usize>(
     buff: [u8; INPUT],
     marker: u8,
 ) -> [u8; OUTPUT] {
     let mut output_buffer: [u8; OUTPUT] = [marker; OUTPUT];
 
-    // Keep track of where the last marker was.
-    // This always has one in the beginning, which is the overhead byte.
-    let mut last_marker = MarkerInfo {
-        index: 0,
-        points_to: 0xff,
-    };
-
-    // Every time we set additional overhead marker, we should increase the offset.
-    // This way we keep track what the relationship is between the input array indices and the
-    // output array indices.
-    let mut overhead_bytes = 1;
-
-    // Loop through all the input bytes.
-    for i in 0..INPUT {
-        // Fetch the value of the input byte array.
-        let value = buff[i];
-
-        if last_marker.points_to == (overhead_bytes + i) {
-            // Update the last marker and set the marker info to this new overhead byte.
-            last_marker.adjust_accordingly(&mut output_buffer, overhead_bytes + i);
-
-            // Say that we have another overhead byte.
-            overhead_bytes += 1;
-        }
-
-        // If the current input value is a marker, adjust the previous marker accordingly and skip
-        // the setting of the value, although it doesn't really matter.
-        if value == marker {
-            // Update the last marker value and info to this new marker.
-            last_marker.adjust_accordingly(&mut output_buffer, overhead_bytes + i);
-
-            continue;
-        }
-
-        // Update the output buffer value
-        output_buffer[overhead_bytes + i] = value;
+    match stuff_slice(&buff, &mut output_buffer, marker) {
+        Ok(_) => output_buffer,
+        Err(EncodeError::OutputTooSmall { needed }) => panic!(
+            "Output buffer has too little space to stuff the input buffer into: needed {needed} bytes"
+        ),
     }
-
-    // For the last byte we update the previous marker.
-    output_buffer[last_marker.index] = (INPUT + overhead_bytes - last_marker.index)
-        .try_into()
-        .unwrap();
-
-    output_buffer
 }
 
 /// Takes an input buffer and a marker value and COBS-decodes it to an output buffer.
@@ -241,66 +229,27 @@ pub fn stuff<const INPUT: usize, const OUTPUT: usize>(
 /// This function panics if the output buffer has too little space to fill the data from the input
 /// buffer with. This never happens if we reserve enough memory for the output, that being two less
 /// bytes than the input buffer.
+///
+/// This shares its decoding logic with [`unstuff_slice`]; see that function if you would rather
+/// receive a [`Result`] than panic. Note that the length returned here is the number of input
+/// bytes consumed, including the terminating marker, whereas [`unstuff_slice`] returns the number
+/// of decoded bytes written.
 pub fn unstuff<const INPUT: usize, const OUTPUT: usize>(
     buff: [u8; INPUT],
     marker: u8,
 ) -> ([u8; OUTPUT], usize) {
     let mut output_buffer = [0; OUTPUT];
 
-    // Keep track when the next marker will be. Initial this will be after the first overhead byte
-    // value. We have to do minus 1 here, because we start our loop at 1 instead of 0.
-    let mut until_next_marker = buff[0] - 1;
-    // If this bits value is 0xff, we know that the next value will be an overhead byte, so keep
-    // track of that.
-    let mut next_is_overhead_byte = buff[0] == 0xff;
-
-    // Keep track of the amount of overhead bytes, so that we can compensate for it when filling
-    // our output buffer.
-    let mut overhead_bytes = 1;
-
-    // We can skip byte since it is the overhead byte we already know about.
-    let mut i = 1;
-
-    let output_buffer_length = loop {
-        // Fetch the value from the input buffer.
-        let value = buff[i];
-
-        // If we value is the marker, we know we have reached the end.
-        if value == marker {
-            break i;
+    match unstuff_core(&buff, &mut output_buffer, marker) {
+        Ok(outcome) => (output_buffer, outcome.consumed),
+        Err(DecodeError::NoTerminator) => panic!("No marker value found!"),
+        Err(DecodeError::OutputTooSmall) => {
+            panic!("Output buffer has too little space to unstuff the input buffer into")
         }
-
-        // If the current character is a marker or a overhead byte.
-        if until_next_marker == 0 {
-            // We know that the distance to the next marker will be the value of this marker.
-            until_next_marker = value;
-
-            // If this byte was a overhead byte.
-            if next_is_overhead_byte {
-                // Keep that that we passed another overhead byte.
-                overhead_bytes += 1;
-            } else {
-                // If it wasn't a overhead byte, we can set this byte to the marker byte.
-                output_buffer[i - overhead_bytes] = marker;
-            }
-
-            // Check whether the next byte will be a overhead byte.
-            next_is_overhead_byte = until_next_marker == 0xff;
-        } else {
-            // If we are not on a marker or overhead byte we can just copy the value over.
-            output_buffer[i - overhead_bytes] = value;
-        }
-
-        until_next_marker -= 1;
-
-        if i < INPUT {
-            i += 1;
-        } else {
-            panic!("No marker value found!");
-        }
-    } + 1;
-
-    (output_buffer, output_buffer_length)
+        Err(DecodeError::Malformed) => panic!("Input buffer is not a well-formed COBS frame"),
+        // `unstuff_core` never checks a checksum, so this can't actually happen.
+        Err(DecodeError::ChecksumMismatch) => unreachable!(),
+    }
 }
 
 #[cfg(test)]
@@ -346,6 +295,24 @@ mod tests {
                 self.encoded_data
             );
         }
+
+        fn assert_stuff_slice(&self) {
+            let mut out = [0; M];
+            assert_eq!(
+                stuff_slice(&self.unencoded_data, &mut out, 0x00),
+                Ok(self.encoded_data.len())
+            );
+            assert_eq!(out, self.encoded_data);
+        }
+
+        fn assert_unstuff_slice(&self) {
+            let mut out = [0; N];
+            assert_eq!(
+                unstuff_slice(&self.encoded_data, &mut out, 0x00),
+                Ok(self.unencoded_data.len())
+            );
+            assert_eq!(out, self.unencoded_data);
+        }
     }
 
     fn get_range<const N: usize>(
@@ -526,4 +493,64 @@ mod tests {
         tv_9().assert_unstuff_then_stuff();
         tv_10().assert_unstuff_then_stuff();
     }
+
+    #[test]
+    fn slice_test_vectors() {
+        TV_1.assert_stuff_slice();
+        TV_2.assert_stuff_slice();
+        TV_3.assert_stuff_slice();
+        TV_4.assert_stuff_slice();
+        TV_5.assert_stuff_slice();
+        tv_6().assert_stuff_slice();
+        tv_7().assert_stuff_slice();
+        tv_8().assert_stuff_slice();
+        tv_9().assert_stuff_slice();
+        tv_10().assert_stuff_slice();
+
+        TV_1.assert_unstuff_slice();
+        TV_2.assert_unstuff_slice();
+        TV_3.assert_unstuff_slice();
+        TV_4.assert_unstuff_slice();
+        TV_5.assert_unstuff_slice();
+        tv_6().assert_unstuff_slice();
+        tv_7().assert_unstuff_slice();
+        tv_8().assert_unstuff_slice();
+        tv_9().assert_unstuff_slice();
+        tv_10().assert_unstuff_slice();
+    }
+
+    #[test]
+    fn stuff_slice_output_too_small() {
+        let mut out = [0; 2];
+        assert_eq!(
+            stuff_slice(&TV_3.unencoded_data, &mut out, 0x00),
+            Err(EncodeError::OutputTooSmall { needed: 6 })
+        );
+    }
+
+    #[test]
+    fn unstuff_slice_output_too_small() {
+        let mut out = [0; 1];
+        assert_eq!(
+            unstuff_slice(&TV_3.encoded_data, &mut out, 0x00),
+            Err(DecodeError::OutputTooSmall)
+        );
+    }
+
+    #[test]
+    fn unstuff_slice_no_terminator() {
+        let mut out = [0; 4];
+        assert_eq!(
+            unstuff_slice(&TV_3.encoded_data[..TV_3.encoded_data.len() - 1], &mut out, 0x00),
+            Err(DecodeError::NoTerminator)
+        );
+    }
+
+    #[test]
+    fn unstuff_slice_leading_marker_is_an_empty_frame() {
+        // Two adjacent marker bytes, as could occur with idle-line fill or a leading sync
+        // delimiter, decode to an empty frame rather than `NoTerminator`.
+        let mut out = [0; 4];
+        assert_eq!(unstuff_slice(&[0x00, 0x03, b'x', b'y', 0x00], &mut out, 0x00), Ok(0));
+    }
 }
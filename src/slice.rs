@@ -0,0 +1,242 @@
+//! Slice-based, fallible variants of [`stuff`](crate::stuff) and [`unstuff`](crate::unstuff).
+//!
+//! These do not require the input and output sizes to be known at compile time and never panic,
+//! which makes them suitable for buffers whose size is only known at runtime.
+
+use core::convert::TryInto;
+
+use crate::{DecodeError, EncodeError};
+
+/// Keeps track of the last overhead/marker byte that was written to the output buffer, so that it
+/// can be patched up once the distance to the next one is known.
+pub(crate) struct MarkerInfo {
+    pub(crate) index: usize,
+    pub(crate) points_to: usize,
+}
+
+impl MarkerInfo {
+    pub(crate) fn adjust_accordingly(&mut self, out_buffer: &mut [u8], new_index: usize) {
+        out_buffer[self.index] = (new_index - self.index).try_into().unwrap();
+
+        self.index = new_index;
+        self.points_to = new_index + 0xff;
+    }
+}
+
+/// Computes the number of bytes that [`stuff_slice`] would write for the given `input` and
+/// `marker`, without actually writing anything.
+///
+/// Takes any exact-size source of bytes rather than just a `&[u8]`, so that
+/// [`stuff_checked`](crate::stuff_checked) can size a payload with a trailing checksum appended
+/// without first having to assemble the two into one contiguous buffer.
+fn encoded_len_from_iter(bytes: impl ExactSizeIterator<Item = u8>, marker: u8) -> usize {
+    // Keep track of where the last marker will end up pointing to.
+    // This always has one in the beginning, which is the overhead byte.
+    let mut last_marker_points_to = 0xff;
+
+    // Every time we would set an additional overhead marker, we should increase the offset.
+    let mut overhead_bytes = 1;
+
+    let len = bytes.len();
+    for (i, value) in bytes.enumerate() {
+        if last_marker_points_to == overhead_bytes + i {
+            last_marker_points_to = overhead_bytes + i + 0xff;
+            overhead_bytes += 1;
+        }
+
+        if value == marker {
+            last_marker_points_to = overhead_bytes + i + 0xff;
+        }
+    }
+
+    // `+ 1` for the trailing marker byte that terminates the encoded frame.
+    len + overhead_bytes + 1
+}
+
+/// Takes an `input` buffer and a `marker` value and COBS-encodes it into the `out` buffer.
+///
+/// Removes all occurrences of the marker value and adds one occurrence at the end. Returns the
+/// number of bytes written to `out`, which is always `input.len() + 2 + input.len() / 254` at
+/// most. Unlike [`stuff`](crate::stuff), this never panics: if `out` is too small, [`EncodeError::OutputTooSmall`]
+/// is returned with the number of bytes that would have been needed, and `out` is left untouched.
+///
+/// # Examples
+///
+/// ```
+/// let mut out = [0u8; 64];
+/// let written = cobs_rs::stuff_slice(b"Hello, world!", &mut out, 0x00).unwrap();
+///
+/// assert!(out[..written - 1].iter().all(|byte| *byte != 0x00));
+/// assert_eq!(out[written - 1], 0x00);
+/// ```
+///
+/// ```
+/// let mut out = [0u8; 4];
+/// let err = cobs_rs::stuff_slice(b"Hello, world!", &mut out, 0x00).unwrap_err();
+///
+/// assert_eq!(err, cobs_rs::EncodeError::OutputTooSmall { needed: 15 });
+/// ```
+pub fn stuff_slice(input: &[u8], out: &mut [u8], marker: u8) -> Result<usize, EncodeError> {
+    stuff_from_iter(input.iter().copied(), out, marker)
+}
+
+/// Same as [`stuff_slice`], but over any exact-size, cloneable source of bytes rather than just a
+/// `&[u8]`.
+///
+/// Shared with [`stuff_checked`](crate::stuff_checked), so that a payload with a trailing checksum
+/// appended can be stuffed directly, without first having to assemble the two into one contiguous
+/// buffer.
+pub(crate) fn stuff_from_iter(
+    bytes: impl ExactSizeIterator<Item = u8> + Clone,
+    out: &mut [u8],
+    marker: u8,
+) -> Result<usize, EncodeError> {
+    let needed = encoded_len_from_iter(bytes.clone(), marker);
+
+    if out.len() < needed {
+        return Err(EncodeError::OutputTooSmall { needed });
+    }
+
+    let out = &mut out[..needed];
+    for byte in out.iter_mut() {
+        *byte = marker;
+    }
+
+    // Keep track of where the last marker was.
+    // This always has one in the beginning, which is the overhead byte.
+    let mut last_marker = MarkerInfo {
+        index: 0,
+        points_to: 0xff,
+    };
+
+    // Every time we set an additional overhead marker, we should increase the offset.
+    let mut overhead_bytes = 1;
+
+    let len = bytes.len();
+    for (i, value) in bytes.enumerate() {
+        if last_marker.points_to == overhead_bytes + i {
+            last_marker.adjust_accordingly(out, overhead_bytes + i);
+            overhead_bytes += 1;
+        }
+
+        if value == marker {
+            last_marker.adjust_accordingly(out, overhead_bytes + i);
+            continue;
+        }
+
+        out[overhead_bytes + i] = value;
+    }
+
+    out[last_marker.index] = (len + overhead_bytes - last_marker.index)
+        .try_into()
+        .unwrap();
+
+    Ok(needed)
+}
+
+/// The result of running the unstuffing state machine over an input buffer.
+pub(crate) struct UnstuffOutcome {
+    /// The number of bytes written to the output buffer.
+    pub(crate) written: usize,
+    /// The number of bytes consumed from the input buffer, including the terminating marker.
+    pub(crate) consumed: usize,
+}
+
+/// Runs the COBS-decoding state machine over `input`, writing decoded bytes into `out`.
+///
+/// Shared by [`unstuff_slice`] and [`unstuff`](crate::unstuff), so that the bookkeeping of
+/// overhead and marker bytes lives in a single place.
+pub(crate) fn unstuff_core(
+    input: &[u8],
+    out: &mut [u8],
+    marker: u8,
+) -> Result<UnstuffOutcome, DecodeError> {
+    let first = *input.first().ok_or(DecodeError::NoTerminator)?;
+
+    if first == marker {
+        // Two adjacent marker bytes (e.g. idle-line fill, or a leading sync delimiter) terminate
+        // an empty frame right away, rather than being treated as a missing terminator.
+        return Ok(UnstuffOutcome {
+            written: 0,
+            consumed: 1,
+        });
+    }
+
+    // Keep track of when the next marker will be. Initially this will be after the first
+    // overhead byte value.
+    let mut until_next_marker = first.wrapping_sub(1);
+    // If this value is 0xff, we know that the next value will be an overhead byte.
+    let mut next_is_overhead_byte = first == 0xff;
+
+    // The number of bytes written to `out` so far.
+    let mut written = 0;
+
+    // We can skip the first byte, since it is the overhead byte we already read above.
+    let mut i = 1;
+
+    loop {
+        let value = *input.get(i).ok_or(DecodeError::NoTerminator)?;
+
+        if value == marker {
+            // In a well-formed frame, the last overhead byte's distance always counts down to
+            // exactly 0 right as the terminator is reached. If it hasn't, the overhead byte
+            // claimed a distance that reaches past the terminator, so the frame is corrupt.
+            if until_next_marker != 0 {
+                return Err(DecodeError::Malformed);
+            }
+
+            return Ok(UnstuffOutcome {
+                written,
+                consumed: i + 1,
+            });
+        }
+
+        if until_next_marker == 0 {
+            // We know that the distance to the next marker will be the value of this marker.
+            until_next_marker = value;
+
+            if next_is_overhead_byte {
+                // This was an overhead byte, so nothing needs to be written for it.
+            } else {
+                // If it wasn't an overhead byte, it stood in for the marker byte.
+                *out.get_mut(written).ok_or(DecodeError::OutputTooSmall)? = marker;
+                written += 1;
+            }
+
+            // Check whether the next byte will be an overhead byte.
+            next_is_overhead_byte = until_next_marker == 0xff;
+        } else {
+            // If we are not on a marker or overhead byte, we can just copy the value over.
+            *out.get_mut(written).ok_or(DecodeError::OutputTooSmall)? = value;
+            written += 1;
+        }
+
+        // Wrapping, just like for the first overhead byte: with a non-zero marker, a corrupt
+        // frame can hold a `0x00` where an overhead byte is expected.
+        until_next_marker = until_next_marker.wrapping_sub(1);
+        i += 1;
+    }
+}
+
+/// Takes an `input` buffer and a `marker` value and COBS-decodes it into the `out` buffer.
+///
+/// Removes all overhead bytes, reinserts the marker where appropriate and stops as soon as a
+/// marker value is found. Returns the number of bytes written to `out`. Unlike
+/// [`unstuff`](crate::unstuff), this
+/// never panics: [`DecodeError::NoTerminator`] is returned if no marker byte is found, and
+/// [`DecodeError::OutputTooSmall`] is returned if `out` is too small to hold the decoded data.
+///
+/// # Examples
+///
+/// ```
+/// let mut encoded = [0u8; 64];
+/// let encoded_len = cobs_rs::stuff_slice(b"Hello, world!", &mut encoded, 0x00).unwrap();
+///
+/// let mut decoded = [0u8; 64];
+/// let written = cobs_rs::unstuff_slice(&encoded[..encoded_len], &mut decoded, 0x00).unwrap();
+///
+/// assert_eq!(&decoded[..written], b"Hello, world!");
+/// ```
+pub fn unstuff_slice(input: &[u8], out: &mut [u8], marker: u8) -> Result<usize, DecodeError> {
+    unstuff_core(input, out, marker).map(|outcome| outcome.written)
+}
@@ -0,0 +1,282 @@
+//! A streaming codec for COBS frames separated by the marker byte in a continuous byte stream,
+//! such as bytes arriving from a serial port.
+
+use crate::{stuff_slice, unstuff_slice, DecodeError, EncodeError};
+
+/// Decodes COBS frames out of a continuous, chunked byte stream.
+///
+/// Incoming bytes are buffered in a caller-supplied backing slice until a marker byte is seen,
+/// at which point [`next_frame`](FrameDecoder::next_frame) can decode the buffered frame. If a
+/// frame turns out to be malformed, or it doesn't fit in the backing buffer, only that frame is
+/// discarded: the decoder resynchronizes at the next marker byte and carries on, rather than
+/// aborting the whole stream.
+///
+/// # Examples
+///
+/// ```
+/// let mut backing = [0u8; 64];
+/// let mut decoder = cobs_rs::FrameDecoder::new(&mut backing, 0x00);
+///
+/// let mut encoded = [0u8; 64];
+/// let n = cobs_rs::stuff_slice(b"frame one", &mut encoded, 0x00).unwrap();
+///
+/// decoder.feed(&encoded[..n]);
+///
+/// let mut decoded = [0u8; 64];
+/// let written = decoder.next_frame(&mut decoded).unwrap().unwrap();
+/// assert_eq!(&decoded[..written], b"frame one");
+/// assert!(decoder.next_frame(&mut decoded).is_none());
+/// ```
+pub struct FrameDecoder<'a> {
+    marker: u8,
+    buf: &'a mut [u8],
+    len: usize,
+    /// Set once the backing buffer has overflowed without finding a marker byte. While set, all
+    /// incoming bytes are discarded until the next marker byte is found, at which point the
+    /// decoder resynchronizes.
+    discarding: bool,
+}
+
+impl<'a> FrameDecoder<'a> {
+    /// Creates a new [`FrameDecoder`] that buffers partial frames in `buf`.
+    ///
+    /// `buf` should be at least as large as the largest stuffed frame you expect to receive.
+    pub fn new(buf: &'a mut [u8], marker: u8) -> Self {
+        Self {
+            marker,
+            buf,
+            len: 0,
+            discarding: false,
+        }
+    }
+
+    /// Feeds a chunk of incoming stream bytes into the decoder, returning the number of bytes
+    /// actually consumed.
+    ///
+    /// Bytes are appended to the backing buffer until it fills up without a marker byte having
+    /// been seen, at which point the in-progress frame is discarded and the decoder resyncs at
+    /// the next marker byte, in this chunk or a later one. Already-terminated frames buffered
+    /// ahead of the in-progress one are left untouched, so a slow caller that hasn't drained them
+    /// yet with [`next_frame`](FrameDecoder::next_frame) doesn't lose them.
+    ///
+    /// If the backing buffer fills up with nothing but undrained, complete frames, there is
+    /// nothing to discard to make room: `feed` stops there and returns fewer bytes than `chunk`
+    /// holds. The caller must drain at least one frame with `next_frame` and feed the remainder of
+    /// `chunk` again.
+    pub fn feed(&mut self, mut chunk: &[u8]) -> usize {
+        let total = chunk.len();
+
+        while !chunk.is_empty() {
+            if self.discarding {
+                match chunk.iter().position(|&byte| byte == self.marker) {
+                    Some(marker_pos) => {
+                        chunk = &chunk[marker_pos + 1..];
+                        self.discarding = false;
+                    }
+                    None => {
+                        chunk = &[];
+                    }
+                }
+                continue;
+            }
+
+            let space = self.buf.len() - self.len;
+            if space == 0 {
+                // Everything up to the last marker byte is already a complete, terminated frame;
+                // only a real in-progress tail after it is an overflow that needs to be dropped.
+                let tail_start = self.buf[..self.len]
+                    .iter()
+                    .rposition(|&b| b == self.marker)
+                    .map_or(0, |last_marker| last_marker + 1);
+
+                if tail_start == self.len {
+                    // No in-progress tail: the buffer is simply full of frames the caller hasn't
+                    // drained yet. Apply backpressure instead of discarding one of them.
+                    break;
+                }
+
+                self.len = tail_start;
+                self.discarding = true;
+                continue;
+            }
+
+            let take = space.min(chunk.len());
+            self.buf[self.len..self.len + take].copy_from_slice(&chunk[..take]);
+            self.len += take;
+            chunk = &chunk[take..];
+        }
+
+        total - chunk.len()
+    }
+
+    /// Decodes the next complete frame buffered so far into `out`.
+    ///
+    /// Returns `None` if no marker byte has been buffered yet, i.e. there is no complete frame to
+    /// decode. Otherwise, the buffered frame (up to and including its marker byte) is removed
+    /// from the backing buffer, whether or not it decoded successfully, so a malformed frame
+    /// never blocks decoding of the frames that follow it.
+    pub fn next_frame(&mut self, out: &mut [u8]) -> Option<Result<usize, DecodeError>> {
+        let marker_pos = self.buf[..self.len]
+            .iter()
+            .position(|&byte| byte == self.marker)?;
+
+        let result = unstuff_slice(&self.buf[..=marker_pos], out, self.marker);
+
+        self.buf.copy_within(marker_pos + 1..self.len, 0);
+        self.len -= marker_pos + 1;
+
+        Some(result)
+    }
+}
+
+/// Encodes payloads into COBS frames that can be written back-to-back into one output stream.
+///
+/// # Examples
+///
+/// ```
+/// let encoder = cobs_rs::FrameEncoder::new(0x00);
+///
+/// let mut out = [0u8; 64];
+/// let mut written = 0;
+/// written += encoder.encode_frame(b"frame one", &mut out[written..]).unwrap();
+/// written += encoder.encode_frame(b"frame two", &mut out[written..]).unwrap();
+/// ```
+pub struct FrameEncoder {
+    marker: u8,
+}
+
+impl FrameEncoder {
+    /// Creates a new [`FrameEncoder`] that terminates every frame with `marker`.
+    pub fn new(marker: u8) -> Self {
+        Self { marker }
+    }
+
+    /// Stuffs `payload` and writes it, including its trailing marker byte, to `out`.
+    ///
+    /// Returns the number of bytes written, so that subsequent frames can be appended right
+    /// after it in the same output stream.
+    pub fn encode_frame(&self, payload: &[u8], out: &mut [u8]) -> Result<usize, EncodeError> {
+        stuff_slice(payload, out, self.marker)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stuffed(payload: &[u8], marker: u8) -> [u8; 64] {
+        let mut out = [0; 64];
+        let n = stuff_slice(payload, &mut out[1..], marker).unwrap();
+        out[0] = n as u8;
+        out
+    }
+
+    #[test]
+    fn decodes_frames_split_across_chunks() {
+        let a = stuffed(b"hello", 0x00);
+        let b = stuffed(b"world", 0x00);
+
+        let mut backing = [0u8; 64];
+        let mut decoder = FrameDecoder::new(&mut backing, 0x00);
+
+        // Feed the first frame split across two chunks, and the second frame whole.
+        decoder.feed(&a[1..1 + a[0] as usize / 2]);
+        decoder.feed(&a[1 + a[0] as usize / 2..1 + a[0] as usize]);
+        decoder.feed(&b[1..1 + b[0] as usize]);
+
+        let mut out = [0u8; 64];
+        assert_eq!(decoder.next_frame(&mut out), Some(Ok(5)));
+        assert_eq!(&out[..5], b"hello");
+
+        assert_eq!(decoder.next_frame(&mut out), Some(Ok(5)));
+        assert_eq!(&out[..5], b"world");
+
+        assert_eq!(decoder.next_frame(&mut out), None);
+    }
+
+    #[test]
+    fn resyncs_after_malformed_frame() {
+        let good = stuffed(b"hi", 0x00);
+
+        let mut backing = [0u8; 64];
+        let mut decoder = FrameDecoder::new(&mut backing, 0x00);
+
+        // An overhead byte (0x09) claiming a distance well past the terminator that follows it.
+        decoder.feed(&[0x09, b'b', b'a', b'd', 0x00]);
+        decoder.feed(&good[1..1 + good[0] as usize]);
+
+        let mut out = [0u8; 64];
+        assert_eq!(decoder.next_frame(&mut out), Some(Err(DecodeError::Malformed)));
+
+        assert_eq!(decoder.next_frame(&mut out), Some(Ok(2)));
+        assert_eq!(&out[..2], b"hi");
+    }
+
+    #[test]
+    fn resyncs_after_buffer_overflow() {
+        let good = stuffed(b"ok", 0x00);
+
+        let mut backing = [0u8; 4];
+        let mut decoder = FrameDecoder::new(&mut backing, 0x00);
+
+        // A frame whose raw bytes never fit the backing buffer before a marker turns up.
+        decoder.feed(b"way too long to ever fit");
+        decoder.feed(&[0x00]);
+        decoder.feed(&good[1..1 + good[0] as usize]);
+
+        let mut out = [0u8; 64];
+        assert_eq!(decoder.next_frame(&mut out), Some(Ok(2)));
+        assert_eq!(&out[..2], b"ok");
+    }
+
+    #[test]
+    fn buffer_overflow_preserves_undrained_complete_frames() {
+        let mut backing = [0u8; 8];
+        let mut decoder = FrameDecoder::new(&mut backing, 0x00);
+
+        // A complete, already-terminated frame the caller hasn't drained yet, followed by an
+        // in-progress one that overflows the backing buffer before reaching a marker byte.
+        decoder.feed(&[0x03, b'a', b'b', 0x00]);
+        decoder.feed(b"wxyzq");
+
+        let mut out = [0u8; 64];
+        assert_eq!(decoder.next_frame(&mut out), Some(Ok(2)));
+        assert_eq!(&out[..2], b"ab");
+        assert_eq!(decoder.next_frame(&mut out), None);
+    }
+
+    #[test]
+    fn applies_backpressure_when_full_of_undrained_frames() {
+        let a = stuffed(b"ab", 0x00);
+        let b = stuffed(b"cd", 0x00);
+
+        // A backing buffer exactly sized for one frame, so a second one has nowhere to go.
+        let mut backing = [0u8; 4];
+        let mut decoder = FrameDecoder::new(&mut backing, 0x00);
+
+        assert_eq!(decoder.feed(&a[1..1 + a[0] as usize]), 4);
+
+        // The buffer is full of nothing but the undrained "ab" frame: `feed` must not discard it
+        // to make room, and instead consumes none of the new frame.
+        assert_eq!(decoder.feed(&b[1..1 + b[0] as usize]), 0);
+
+        let mut out = [0u8; 64];
+        assert_eq!(decoder.next_frame(&mut out), Some(Ok(2)));
+        assert_eq!(&out[..2], b"ab");
+
+        // Now that "ab" has been drained, re-feeding the same bytes succeeds.
+        assert_eq!(decoder.feed(&b[1..1 + b[0] as usize]), 4);
+        assert_eq!(decoder.next_frame(&mut out), Some(Ok(2)));
+        assert_eq!(&out[..2], b"cd");
+    }
+
+    #[test]
+    fn encode_frame_appends_marker() {
+        let encoder = FrameEncoder::new(0x00);
+
+        let mut out = [0u8; 64];
+        let written = encoder.encode_frame(b"hi", &mut out).unwrap();
+
+        assert_eq!(&out[..written], &[0x03, b'h', b'i', 0x00]);
+    }
+}
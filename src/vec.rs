@@ -0,0 +1,87 @@
+//! `alloc`-gated encoding and decoding into auto-growing [`Vec<u8>`] buffers.
+//!
+//! Requires the `alloc` cargo feature.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{stuff_slice, unstuff_slice, DecodeError, EncodeError};
+
+/// COBS-encodes `input` into a freshly allocated [`Vec<u8>`], growing it on demand.
+///
+/// The returned `Vec` holds exactly the stuffed data, including its trailing marker byte, with no
+/// left-over padding. This avoids having to hand-compute `2 + input.len() / 254` for the output
+/// buffer, as is necessary with [`stuff`](crate::stuff) and [`stuff_slice`].
+///
+/// # Examples
+///
+/// ```
+/// let encoded = cobs_rs::stuff_to_vec(b"Hello, world!", 0x00);
+///
+/// assert!(encoded[..encoded.len() - 1].iter().all(|byte| *byte != 0x00));
+/// assert_eq!(*encoded.last().unwrap(), 0x00);
+/// ```
+pub fn stuff_to_vec(input: &[u8], marker: u8) -> Vec<u8> {
+    let mut out = vec![marker; 2 + input.len() / 254 + input.len()];
+
+    loop {
+        match stuff_slice(input, &mut out, marker) {
+            Ok(written) => {
+                out.truncate(written);
+                return out;
+            }
+            Err(EncodeError::OutputTooSmall { needed }) => out.resize(needed, marker),
+        }
+    }
+}
+
+/// COBS-decodes `input` into a freshly allocated [`Vec<u8>`], growing it on demand.
+///
+/// The returned `Vec` holds exactly the decoded data, with no left-over padding.
+///
+/// # Examples
+///
+/// ```
+/// let encoded = cobs_rs::stuff_to_vec(b"Hello, world!", 0x00);
+/// let decoded = cobs_rs::unstuff_to_vec(&encoded, 0x00).unwrap();
+///
+/// assert_eq!(decoded, b"Hello, world!");
+/// ```
+pub fn unstuff_to_vec(input: &[u8], marker: u8) -> Result<Vec<u8>, DecodeError> {
+    let mut out = vec![0; input.len()];
+
+    loop {
+        match unstuff_slice(input, &mut out, marker) {
+            Ok(written) => {
+                out.truncate(written);
+                return Ok(out);
+            }
+            Err(DecodeError::OutputTooSmall) => {
+                let new_len = out.len() * 2 + 1;
+                out.resize(new_len, 0);
+            }
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stuff_to_vec_round_trips() {
+        let data: Vec<u8> = (0..=255).collect();
+
+        let encoded = stuff_to_vec(&data, 0x00);
+        assert!(encoded[..encoded.len() - 1].iter().all(|&byte| byte != 0x00));
+
+        let decoded = unstuff_to_vec(&encoded, 0x00).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn unstuff_to_vec_propagates_errors() {
+        assert_eq!(unstuff_to_vec(&[], 0x00), Err(DecodeError::NoTerminator));
+    }
+}